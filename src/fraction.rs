@@ -0,0 +1,125 @@
+//! A minimal exact rational number, used where repeated division would otherwise lose precision
+//! (e.g. STV surplus transfers).
+
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// An exact rational number, always kept reduced to lowest terms with a positive denominator.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Fraction {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Fraction {
+    /// Constructs `numerator / denominator`, reduced to lowest terms. Panics if `denominator` is
+    /// zero.
+    pub fn new(numerator: i64, denominator: i64) -> Fraction {
+        assert!(denominator != 0, "denominator must not be zero");
+
+        let sign = if denominator < 0 { -1 } else { 1 };
+        let (numerator, denominator) = (numerator * sign, denominator * sign);
+        let divisor = gcd(numerator.abs(), denominator);
+        if divisor == 0 {
+            return Fraction { numerator: 0, denominator: 1 };
+        }
+
+        Fraction {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    /// Constructs the whole number `value`, i.e. `value / 1`.
+    pub fn whole(value: i64) -> Fraction {
+        Fraction::new(value, 1)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Add for Fraction {
+    type Output = Fraction;
+    fn add(self, other: Fraction) -> Fraction {
+        Fraction::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Sub for Fraction {
+    type Output = Fraction;
+    fn sub(self, other: Fraction) -> Fraction {
+        Fraction::new(
+            self.numerator * other.denominator - other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Mul for Fraction {
+    type Output = Fraction;
+    fn mul(self, other: Fraction) -> Fraction {
+        Fraction::new(self.numerator * other.numerator, self.denominator * other.denominator)
+    }
+}
+
+impl Div for Fraction {
+    type Output = Fraction;
+    fn div(self, other: Fraction) -> Fraction {
+        Fraction::new(self.numerator * other.denominator, self.denominator * other.numerator)
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Denominators are always positive, so cross-multiplying preserves ordering.
+        (self.numerator * other.denominator).cmp(&(other.numerator * self.denominator))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reduces_to_lowest_terms() {
+        assert_eq!(Fraction::new(2, 4), Fraction::new(1, 2));
+    }
+
+    #[test]
+    fn normalizes_negative_denominator() {
+        assert_eq!(Fraction::new(1, -2), Fraction::new(-1, 2));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Fraction::new(1, 2) + Fraction::new(1, 3), Fraction::new(5, 6));
+        assert_eq!(Fraction::new(1, 2) - Fraction::new(1, 3), Fraction::new(1, 6));
+        assert_eq!(Fraction::new(1, 2) * Fraction::new(2, 3), Fraction::new(1, 3));
+        assert_eq!(Fraction::new(1, 2) / Fraction::new(1, 4), Fraction::whole(2));
+    }
+
+    #[test]
+    fn ordering_compares_across_denominators() {
+        assert!(Fraction::new(1, 3) < Fraction::new(1, 2));
+        assert!(Fraction::new(2, 4) <= Fraction::new(1, 2));
+    }
+}
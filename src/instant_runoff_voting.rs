@@ -1,16 +1,44 @@
 use crate::{unique_candidates, Ballot, BallotSlice, Candidate};
 use std::collections::HashMap;
 
+/// How to choose which candidate to eliminate when two or more candidates tie for fewest
+/// first-choice votes in a round of [`instant_runoff_vote`].
+///
+/// `Forwards` and `Backwards` look at the tied candidates' tallies from earlier rounds to break
+/// the tie deterministically; `Random` breaks it with a seeded PRNG. Each carries its own `seed`,
+/// which `Forwards`/`Backwards` fall back to when no earlier round distinguishes the tied
+/// candidates (including when the tie happens in the first round).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TieBreak {
+    /// Eliminate whichever tied candidate had strictly fewest votes at the earliest round where
+    /// the tied candidates' tallies differed.
+    Forwards { seed: u64 },
+    /// Eliminate whichever tied candidate had strictly fewest votes at the most recent round
+    /// where the tied candidates' tallies differed.
+    Backwards { seed: u64 },
+    /// Eliminate a tied candidate chosen by a seeded PRNG, so results are reproducible.
+    Random { seed: u64 },
+}
+
 /// Instant Runoff Vote. If there are more than one candidates, remove the one with the fewest first
-/// choice votes and repeat.
+/// choice votes and repeat. Ties for fewest votes are resolved by `tie_break`.
 ///
 /// Requires that each ballot has unique candidates (no repeats).
-pub fn instant_runoff_vote(mut ballots: Vec<Ballot>) -> Candidate {
-    for ballot in ballots.iter() {
+pub fn instant_runoff_vote(ballots: Vec<Ballot>, tie_break: TieBreak) -> Candidate {
+    let weighted = ballots.into_iter().map(|ballot| (ballot, 1)).collect();
+    instant_runoff_vote_weighted(weighted, tie_break)
+}
+
+/// Same as [`instant_runoff_vote`], but each ballot carries an integer `weight`, so identical
+/// ballots can be collapsed into a single `(ballot, weight)` entry instead of being repeated.
+pub fn instant_runoff_vote_weighted(mut ballots: Vec<(Ballot, i32)>, tie_break: TieBreak) -> Candidate {
+    for (ballot, _) in ballots.iter() {
         // TODO, there may be stricter requirements here.
         assert!(unique_candidates(ballot));
     }
 
+    let mut history: Vec<HashMap<Candidate, i32>> = vec![];
+
     loop {
         let tally = first_choice_tally(&ballots);
 
@@ -19,15 +47,87 @@ pub fn instant_runoff_vote(mut ballots: Vec<Ballot>) -> Candidate {
             return *tally.keys().next().unwrap();
         }
 
-        let (weakest_candidate, _) = tally.iter().min_by_key(|(_, count)| *count).unwrap();
-        remove_candidate(&mut ballots, *weakest_candidate);
+        history.push(tally.clone());
+
+        let min_count = *tally.values().min().unwrap();
+        let tied: Vec<Candidate> = tally
+            .iter()
+            .filter(|(_, count)| **count == min_count)
+            .map(|(candidate, _)| *candidate)
+            .collect();
+
+        let weakest_candidate = if tied.len() == 1 {
+            tied[0]
+        } else {
+            break_tie(&tied, &history, tie_break)
+        };
+
+        remove_candidate(&mut ballots, weakest_candidate);
+    }
+}
+
+/// Picks which of the tied candidates to eliminate, according to `tie_break`.
+fn break_tie(
+    tied: &[Candidate],
+    history: &[HashMap<Candidate, i32>],
+    tie_break: TieBreak,
+) -> Candidate {
+    // Used to vary the random fallback from round to round, even with the same seed.
+    let round = history.len();
+
+    match tie_break {
+        TieBreak::Forwards { seed } => history
+            .iter()
+            .find_map(|tally| strictly_weakest(tied, tally))
+            .unwrap_or_else(|| random_pick(tied, seed, round)),
+        TieBreak::Backwards { seed } => history
+            .iter()
+            .rev()
+            .find_map(|tally| strictly_weakest(tied, tally))
+            .unwrap_or_else(|| random_pick(tied, seed, round)),
+        TieBreak::Random { seed } => random_pick(tied, seed, round),
+    }
+}
+
+/// If exactly one of `tied` had strictly fewest votes in `tally`, returns it. Otherwise (the
+/// candidates were still tied at this round) returns `None`.
+fn strictly_weakest(tied: &[Candidate], tally: &HashMap<Candidate, i32>) -> Option<Candidate> {
+    let counts: Vec<(Candidate, i32)> = tied.iter().map(|&c| (c, tally[&c])).collect();
+    let min_count = counts.iter().map(|(_, count)| *count).min()?;
+
+    let mut at_min = counts.iter().filter(|(_, count)| *count == min_count);
+    let weakest = at_min.next()?.0;
+    if at_min.next().is_some() {
+        // More than one candidate still tied at this round.
+        None
+    } else {
+        Some(weakest)
     }
 }
 
-fn first_choice_tally(ballots: &[Ballot]) -> HashMap<Candidate, usize> {
+/// Deterministically picks one of `tied` using a seeded PRNG, so the same seed and situation
+/// always produce the same elimination.
+fn random_pick(tied: &[Candidate], seed: u64, round: usize) -> Candidate {
+    let mut sorted = tied.to_vec();
+    sorted.sort_by_key(|candidate| candidate.id);
+
+    let index = splitmix64(seed, round as u64) as usize % sorted.len();
+    sorted[index]
+}
+
+/// A small, fast, seeded PRNG step (SplitMix64). Not cryptographically secure, just deterministic.
+fn splitmix64(seed: u64, stream: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add(stream.wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+fn first_choice_tally(ballots: &[(Ballot, i32)]) -> HashMap<Candidate, i32> {
     let mut counts = HashMap::new();
-    for ballot in ballots {
-        *counts.entry(first_choice_candidate(ballot)).or_insert(0) += 1;
+    for (ballot, weight) in ballots {
+        *counts.entry(first_choice_candidate(ballot)).or_insert(0) += weight;
     }
 
     counts
@@ -42,8 +142,8 @@ fn first_choice_candidate(ballot: &BallotSlice) -> Candidate {
 }
 
 /// Removes the candidate from the ballots.
-fn remove_candidate(ballots: &mut Vec<Ballot>, candidate: Candidate) {
-    for ballot in ballots {
+fn remove_candidate(ballots: &mut [(Ballot, i32)], candidate: Candidate) {
+    for (ballot, _) in ballots.iter_mut() {
         let mut i = 0;
         while i < ballot.len() {
             if ballot[i].0 == candidate {
@@ -63,6 +163,7 @@ mod test {
     const ALICE: Candidate = Candidate { id: 0 };
     const BOB: Candidate = Candidate { id: 1 };
     const CHAD: Candidate = Candidate { id: 2 };
+    const DAVE: Candidate = Candidate { id: 3 };
 
     #[test]
     fn simple_instant_runoff_vote() {
@@ -74,7 +175,96 @@ mod test {
             vec![(BOB, 1), (CHAD, 2), (ALICE, 3)],
         ];
 
-        let winner = instant_runoff_vote(ballots);
+        let winner = instant_runoff_vote(ballots, TieBreak::Forwards { seed: 0 });
         assert_eq!(winner, BOB);
     }
+
+    #[test]
+    fn forwards_tie_break_falls_back_to_seed_when_no_round_distinguishes() {
+        // ALICE and BOB tie for fewest votes in the first round, so there is no earlier round to
+        // tell them apart and Forwards must fall back to its seed.
+        let ballots = vec![
+            vec![(ALICE, 1), (CHAD, 2)],
+            vec![(BOB, 1), (CHAD, 2)],
+            vec![(CHAD, 1), (ALICE, 2)],
+            vec![(CHAD, 1), (BOB, 2)],
+        ];
+
+        // With only 2 candidates tied and no earlier round distinguishing them, the outcome is
+        // deterministic for a given seed.
+        let winner_a = instant_runoff_vote(ballots.clone(), TieBreak::Forwards { seed: 42 });
+        let winner_b = instant_runoff_vote(ballots, TieBreak::Forwards { seed: 42 });
+        assert_eq!(winner_a, winner_b);
+    }
+
+    #[test]
+    fn backwards_tie_break_falls_back_to_seed_when_no_round_distinguishes() {
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3), (DAVE, 4)],
+            vec![(BOB, 1), (ALICE, 2), (CHAD, 3), (DAVE, 4)],
+            vec![(CHAD, 1), (ALICE, 2), (BOB, 3), (DAVE, 4)],
+            vec![(DAVE, 1), (ALICE, 2), (BOB, 3), (CHAD, 4)],
+        ];
+
+        let winner = instant_runoff_vote(ballots, TieBreak::Backwards { seed: 7 });
+        // Every candidate starts with exactly one first-choice vote, so the first round is a full
+        // tie, and candidates tied in a later round had equal tallies in every earlier round; each
+        // tie falls back to the seed, and the result still must be one of the candidates.
+        assert!([ALICE, BOB, CHAD, DAVE].contains(&winner));
+    }
+
+    #[test]
+    fn forwards_and_backwards_scan_in_opposite_directions_and_can_disagree() {
+        // ALICE and BOB's standings cross over between rounds: round 1 has ALICE behind BOB,
+        // round 2 (after CHAD is eliminated) has BOB behind ALICE, and the final round (after
+        // DAVE is eliminated) ties them exactly. Forwards must eliminate ALICE, the weaker of
+        // the pair at the *earliest* differing round (round 1); Backwards must eliminate BOB,
+        // the weaker at the *most recent* differing round (round 2) — so the two strategies
+        // produce different winners here.
+        let mut ballots = vec![];
+        ballots.extend(vec![vec![(ALICE, 1), (BOB, 2), (CHAD, 3), (DAVE, 4)]; 3]);
+        ballots.extend(vec![vec![(BOB, 1), (ALICE, 2), (CHAD, 3), (DAVE, 4)]; 4]);
+        ballots.extend(vec![vec![(CHAD, 1), (ALICE, 2), (BOB, 3), (DAVE, 4)]; 2]);
+        ballots.extend(vec![vec![(DAVE, 1), (ALICE, 2), (BOB, 3), (CHAD, 4)]; 1]);
+        ballots.extend(vec![vec![(DAVE, 1), (BOB, 2), (ALICE, 3), (CHAD, 4)]; 2]);
+
+        let forwards_winner = instant_runoff_vote(ballots.clone(), TieBreak::Forwards { seed: 0 });
+        let backwards_winner = instant_runoff_vote(ballots, TieBreak::Backwards { seed: 0 });
+
+        assert_eq!(forwards_winner, BOB);
+        assert_eq!(backwards_winner, ALICE);
+    }
+
+    #[test]
+    fn random_tie_break_is_reproducible_for_the_same_seed() {
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(BOB, 1), (ALICE, 2)],
+        ];
+
+        let winner_a = instant_runoff_vote(ballots.clone(), TieBreak::Random { seed: 123 });
+        let winner_b = instant_runoff_vote(ballots, TieBreak::Random { seed: 123 });
+        assert_eq!(winner_a, winner_b);
+    }
+
+    #[test]
+    fn weighted_ballots_collapse_identical_repeats() {
+        let unweighted = vec![
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3)],
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3)],
+            vec![(CHAD, 1), (BOB, 2), (ALICE, 3)],
+            vec![(BOB, 1), (CHAD, 2), (ALICE, 3)],
+            vec![(BOB, 1), (CHAD, 2), (ALICE, 3)],
+        ];
+        let weighted = vec![
+            (vec![(ALICE, 1), (BOB, 2), (CHAD, 3)], 2),
+            (vec![(CHAD, 1), (BOB, 2), (ALICE, 3)], 1),
+            (vec![(BOB, 1), (CHAD, 2), (ALICE, 3)], 2),
+        ];
+
+        assert_eq!(
+            instant_runoff_vote(unweighted, TieBreak::Forwards { seed: 0 }),
+            instant_runoff_vote_weighted(weighted, TieBreak::Forwards { seed: 0 }),
+        );
+    }
 }
@@ -1,5 +1,8 @@
+pub mod fraction;
 pub mod instant_runoff_voting;
 pub mod schulze_method;
+pub mod stv;
+pub mod tally;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Candidate {
@@ -84,6 +87,12 @@ impl PairwisePreferences {
 
     // Adds a new ballot to the total count.
     fn count_ballot(&mut self, ballot: &BallotSlice) {
+        self.count_ballot_weighted(ballot, 1);
+    }
+
+    // Adds a new ballot to the total count, counting it `weight` times. Lets identical ballots
+    // be collapsed into a single call instead of being repeated.
+    fn count_ballot_weighted(&mut self, ballot: &BallotSlice, weight: i32) {
         for i in 0..ballot.len() {
             for j in (i + 1)..ballot.len() {
                 let (candidate_a, rank_a) = ballot[i];
@@ -92,9 +101,9 @@ impl PairwisePreferences {
                 use std::cmp::Ordering;
                 match rank_a.cmp(&rank_b) {
                     // candidate_a is preferred to candidate_b
-                    Ordering::Less => self.counts[candidate_a.id][candidate_b.id] += 1,
+                    Ordering::Less => self.counts[candidate_a.id][candidate_b.id] += weight,
                     // candidate_b is preferred to candidate_a
-                    Ordering::Greater => self.counts[candidate_b.id][candidate_a.id] += 1,
+                    Ordering::Greater => self.counts[candidate_b.id][candidate_a.id] += weight,
                     // otherwise rank_a == rank_b, do not change the count
                     Ordering::Equal => {}
                 }
@@ -1,5 +1,24 @@
 use crate::{Candidate, Ballot, BallotSlice, unique_candidates, PairwisePreferences};
 
+/// Measure of the strength of a link `e -> f` in the majority graph, used to weight the edges
+/// before searching for widest paths.
+///
+/// See [reference](https://en.wikipedia.org/wiki/Schulze_method#Strength_of_a_link) for more
+/// information on the different variants.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Variant {
+    /// Strength is the number of voters preferring `e` to `f`. This is the original Schulze
+    /// method. Strength is 0 unless `e` outright beats `f`.
+    Winning,
+    /// Strength is the margin: voters preferring `e` to `f` minus voters preferring `f` to `e`.
+    /// Unlike `Winning`, this can be negative.
+    Margin,
+    /// Strength is the ratio of voters preferring `e` to `f` over voters preferring `f` to `e`.
+    /// When no voters prefer `f` to `e`, the strength is treated as infinite, unless no voters
+    /// prefer `e` to `f` either, in which case the strength is 0.
+    Ratio,
+}
+
 /// Schulze method election. Ballots give a list of candidates and a number ranking.
 /// Lower numbers are more preferred candidates. Each candidate can only be listed
 /// once on a ballot. Multiple candidates may have the same ranking.
@@ -9,15 +28,32 @@ use crate::{Candidate, Ballot, BallotSlice, unique_candidates, PairwisePreferenc
 ///
 /// Ties are broken arbitrarily.
 ///
+/// Uses the [`Variant::Winning`] link-strength measure. See [`schulze_method_with_variant`] to
+/// pick a different measure.
+///
 /// See [reference](https://en.wikipedia.org/wiki/Schulze_method) for more information.
 pub fn schulze_method(votes: Vec<Ballot>) -> Vec<Candidate> {
+    schulze_method_with_variant(votes, Variant::Winning)
+}
+
+/// Schulze method election, same as [`schulze_method`], but lets the caller choose which
+/// [`Variant`] of link strength is used to compare paths between candidates. Some bylaws specify
+/// margin or ratio based strength rather than the classic winning votes measure.
+pub fn schulze_method_with_variant(votes: Vec<Ballot>, variant: Variant) -> Vec<Candidate> {
     // Check that the ballots are valid.
     for ballot in votes.iter() {
         assert!(valid_ballot(ballot));
     }
 
     let count = PairwisePreferences::from_ballots(&votes);
-    let widest_paths = floyd_warshall_widest_paths(&count.counts);
+    schulze_ranking(&count, variant)
+}
+
+/// The Schulze method ranking over an already-built [`PairwisePreferences`], e.g. one
+/// accumulated incrementally by [`crate::tally::Tally`].
+pub(crate) fn schulze_ranking(count: &PairwisePreferences, variant: Variant) -> Vec<Candidate> {
+    let strengths = link_strengths(&count.counts, variant);
+    let widest_paths = floyd_warshall_widest_paths(&strengths);
 
     let mut candidates_to_sort = count
         .candidates()
@@ -29,7 +65,7 @@ pub fn schulze_method(votes: Vec<Ballot>) -> Vec<Candidate> {
 
 /// schulze_method, but only returns the first candidate.
 pub fn schulze_method_single(votes: Vec<Ballot>) -> Candidate {
-    *schulze_method(votes).get(0).expect("Expecting there to be at least one candidate.")
+    *schulze_method(votes).first().expect("Expecting there to be at least one candidate.")
 }
 
 // Checks if the ballot is valid.
@@ -37,8 +73,49 @@ fn valid_ballot(ballot: &BallotSlice) -> bool {
     unique_candidates(ballot)
 }
 
+/// Turns the raw pairwise `counts[e][f]` tally into the link strength used to weight the edge
+/// `e -> f`, according to `variant`.
+fn link_strengths(counts: &[Vec<i32>], variant: Variant) -> Vec<Vec<f64>> {
+    let dim = counts.len();
+    let mut strengths = vec![vec![0.0; dim]; dim];
+
+    for e in 0..dim {
+        for f in 0..dim {
+            if e == f {
+                continue;
+            }
+
+            let winning = counts[e][f];
+            let losing = counts[f][e];
+            strengths[e][f] = match variant {
+                Variant::Winning => {
+                    if winning > losing {
+                        winning as f64
+                    } else {
+                        0.0
+                    }
+                }
+                Variant::Margin => (winning - losing) as f64,
+                Variant::Ratio => {
+                    if winning == 0 && losing == 0 {
+                        // Never compared (or a genuine 0-0 tie): contribute no strength, rather
+                        // than acting as an infinite-width bridge between unrelated candidates.
+                        0.0
+                    } else if losing == 0 {
+                        f64::INFINITY
+                    } else {
+                        winning as f64 / losing as f64
+                    }
+                }
+            };
+        }
+    }
+
+    strengths
+}
+
 /// Returns widest_path[x][y] which is the capacity of the widest path from x to y.
-fn floyd_warshall_widest_paths(weights: &[Vec<i32>]) -> Vec<Vec<i32>> {
+fn floyd_warshall_widest_paths(weights: &[Vec<f64>]) -> Vec<Vec<f64>> {
     let dim = weights.len();
     if dim == 0 {
         return vec![];
@@ -49,7 +126,7 @@ fn floyd_warshall_widest_paths(weights: &[Vec<i32>]) -> Vec<Vec<i32>> {
     #[allow(clippy::needless_range_loop)]
     for i in 0..dim {
         // self loop assumed to have maximum width.
-        current_widest[i][i] = i32::MAX;
+        current_widest[i][i] = f64::INFINITY;
     }
 
     // For each k, a new node to introduce into the possible paths, check if k can be used in a new
@@ -57,7 +134,7 @@ fn floyd_warshall_widest_paths(weights: &[Vec<i32>]) -> Vec<Vec<i32>> {
     for k in 0..dim {
         for i in 0..dim {
             for j in 0..dim {
-                let width_using_k = std::cmp::min(current_widest[i][k], current_widest[k][j]);
+                let width_using_k = current_widest[i][k].min(current_widest[k][j]);
                 if current_widest[i][j] < width_using_k {
                     current_widest[i][j] = width_using_k;
                 }
@@ -68,9 +145,154 @@ fn floyd_warshall_widest_paths(weights: &[Vec<i32>]) -> Vec<Vec<i32>> {
     current_widest
 }
 
+/// Returns the Smith set: the smallest non-empty set of candidates who each pairwise beat every
+/// candidate outside the set (candidates who tie are kept together in the same set).
+///
+/// A [Condorcet winner](https://en.wikipedia.org/wiki/Condorcet_winner_criterion) exists exactly
+/// when `smith_set(votes).len() == 1`. See [`schwartz_set`] for the related, sometimes smaller,
+/// set that does not merge candidates who only tie.
+pub fn smith_set(votes: Vec<Ballot>) -> Vec<Candidate> {
+    for ballot in votes.iter() {
+        assert!(valid_ballot(ballot));
+    }
+
+    let count = PairwisePreferences::from_ballots(&votes);
+    let graph = majority_graph(&count.counts, true);
+    undominated_candidates(&graph)
+}
+
+/// Returns the Schwartz set: the union of the undominated strongly connected components of the
+/// majority graph, using only strict pairwise victories as edges (a tie does not connect two
+/// candidates).
+///
+/// This can be a proper subset of the [`smith_set`], since ties no longer force candidates into
+/// the same component.
+pub fn schwartz_set(votes: Vec<Ballot>) -> Vec<Candidate> {
+    for ballot in votes.iter() {
+        assert!(valid_ballot(ballot));
+    }
+
+    let count = PairwisePreferences::from_ballots(&votes);
+    let graph = majority_graph(&count.counts, false);
+    undominated_candidates(&graph)
+}
+
+/// Builds the majority graph as an adjacency list: `graph[x]` lists every `y` with an edge
+/// `x -> y`. An edge is added whenever `x` beats `y`; if `include_ties` is set, an edge is also
+/// added whenever `x` and `y` tie.
+fn majority_graph(counts: &[Vec<i32>], include_ties: bool) -> Vec<Vec<usize>> {
+    let dim = counts.len();
+    let mut graph = vec![vec![]; dim];
+    #[allow(clippy::needless_range_loop)]
+    for x in 0..dim {
+        for y in 0..dim {
+            if x == y {
+                continue;
+            }
+            if counts[x][y] > counts[y][x] || (include_ties && counts[x][y] == counts[y][x]) {
+                graph[x].push(y);
+            }
+        }
+    }
+    graph
+}
+
+/// Condenses `graph` into strongly connected components (via [`tarjan_scc`]) and returns the
+/// union of every component that has no incoming edge from another component, i.e. the
+/// components that nobody outside the set dominates.
+fn undominated_candidates(graph: &[Vec<usize>]) -> Vec<Candidate> {
+    let components = tarjan_scc(graph);
+
+    let mut component_of = vec![0; graph.len()];
+    for (i, component) in components.iter().enumerate() {
+        for &node in component {
+            component_of[node] = i;
+        }
+    }
+
+    let mut has_incoming_edge = vec![false; components.len()];
+    for (node, neighbors) in graph.iter().enumerate() {
+        for &neighbor in neighbors {
+            if component_of[node] != component_of[neighbor] {
+                has_incoming_edge[component_of[neighbor]] = true;
+            }
+        }
+    }
+
+    components
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !has_incoming_edge[*i])
+        .flat_map(|(_, component)| component)
+        .map(Candidate::from)
+        .collect()
+}
+
+/// Tarjan's strongly connected components algorithm. Returns each component as a list of node
+/// indices, in reverse topological order of the condensation DAG.
+fn tarjan_scc(graph: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State {
+        index_counter: usize,
+        index: Vec<Option<usize>>,
+        lowlink: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn strong_connect(node: usize, graph: &[Vec<usize>], state: &mut State) {
+        state.index[node] = Some(state.index_counter);
+        state.lowlink[node] = state.index_counter;
+        state.index_counter += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for &neighbor in &graph[node] {
+            if state.index[neighbor].is_none() {
+                strong_connect(neighbor, graph, state);
+                state.lowlink[node] = state.lowlink[node].min(state.lowlink[neighbor]);
+            } else if state.on_stack[neighbor] {
+                state.lowlink[node] = state.lowlink[node].min(state.index[neighbor].unwrap());
+            }
+        }
+
+        if state.lowlink[node] == state.index[node].unwrap() {
+            let mut component = vec![];
+            loop {
+                let member = state.stack.pop().unwrap();
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let dim = graph.len();
+    let mut state = State {
+        index_counter: 0,
+        index: vec![None; dim],
+        lowlink: vec![0; dim],
+        on_stack: vec![false; dim],
+        stack: vec![],
+        components: vec![],
+    };
+
+    for node in 0..dim {
+        if state.index[node].is_none() {
+            strong_connect(node, graph, &mut state);
+        }
+    }
+
+    state.components
+}
+
 /// For the given candidate, count the number of challengers that the candidate beats.
-fn preferred_above_count(preferences: &[Vec<i32>], candidate: Candidate) -> usize {
+fn preferred_above_count(preferences: &[Vec<f64>], candidate: Candidate) -> usize {
     let mut count = 0;
+    #[allow(clippy::needless_range_loop)]
     for other in 0..preferences.len() {
         if candidate.id == other { continue; }
         if preferences[candidate.id][other] >= preferences[other][candidate.id] {
@@ -96,10 +318,10 @@ mod test {
         fn prop_floyd_warshall_widest_path_finds_widest((edge_weights, path) in weights_and_path()) {
             let widest = floyd_warshall_widest_paths(&edge_weights);
 
-            let mut path_width = i32::MAX;
+            let mut path_width = f64::INFINITY;
             for window in path.windows(2) {
                 let current_width = edge_weights[window[0]][window[1]];
-                path_width = std::cmp::min(path_width, current_width);
+                path_width = path_width.min(current_width);
             }
 
             let first = path[0];
@@ -109,9 +331,9 @@ mod test {
         }
     }
 
-    fn square_vec(length: impl Strategy<Value = usize>) -> impl Strategy<Value = Vec<Vec<i32>>> {
+    fn square_vec(length: impl Strategy<Value = usize>) -> impl Strategy<Value = Vec<Vec<f64>>> {
         use proptest::collection::vec;
-        length.prop_flat_map(|length| vec(vec(0..100, length), length))
+        length.prop_flat_map(|length| vec(vec(0.0..100.0, length), length))
     }
 
     fn shuffled_subsequence(values: std::ops::Range<usize>, size: usize) -> impl Strategy<Value = Vec<usize>> {
@@ -120,7 +342,7 @@ mod test {
         subsequence(values, size).prop_shuffle()
     }
 
-    fn weights_and_path() -> impl Strategy<Value=(Vec<Vec<i32>>, Vec<usize>)>{
+    fn weights_and_path() -> impl Strategy<Value=(Vec<Vec<f64>>, Vec<usize>)>{
         // So far, this is only used in props that make sense with 2 or more length
         let length = 2..10usize;
         length.prop_flat_map(|num_vertecies| {
@@ -172,75 +394,164 @@ mod test {
     fn wiki_floyd_warshall() {
         let ballots = wiki_ballots();
         let count = PairwisePreferences::from_ballots(&ballots);
-        let widest_paths = floyd_warshall_widest_paths(&count.counts);
+        let strengths = link_strengths(&count.counts, Variant::Winning);
+        let widest_paths = floyd_warshall_widest_paths(&strengths);
         assert_eq!(widest_paths, vec![
-            vec![i32::MAX, 28, 28, 30, 24],
-            vec![25, i32::MAX, 28, 33, 24],
-            vec![25, 29, i32::MAX, 29, 24],
-            vec![25, 28, 28, i32::MAX, 24],
-            vec![25, 28, 28, 31, i32::MAX],
+            vec![f64::INFINITY, 28.0, 28.0, 30.0, 24.0],
+            vec![25.0, f64::INFINITY, 28.0, 33.0, 24.0],
+            vec![25.0, 29.0, f64::INFINITY, 29.0, 24.0],
+            vec![25.0, 28.0, 28.0, f64::INFINITY, 24.0],
+            vec![25.0, 28.0, 28.0, 31.0, f64::INFINITY],
         ]);
     }
 
+    #[test]
+    fn wiki_schulze_method_margin_matches_winning() {
+        // For this example, the margin variant picks the same winner as winning votes.
+        let ballots = wiki_ballots();
+        let winner = *schulze_method_with_variant(ballots, Variant::Margin)
+            .get(0)
+            .unwrap();
+        assert_eq!(winner, ELSA);
+    }
+
     #[test]
     fn simple_floyd_warshall() {
         let edge_weights = vec![
-            vec![0, 5, 0],
-            vec![0, 0, 5],
-            vec![0, 0, 0],
+            vec![0.0, 5.0, 0.0],
+            vec![0.0, 0.0, 5.0],
+            vec![0.0, 0.0, 0.0],
         ];
         let widest_paths = floyd_warshall_widest_paths(&edge_weights);
         assert_eq!(widest_paths, vec![
-            vec![i32::MAX, 5, 5],
-            vec![0, i32::MAX, 5],
-            vec![0, 0, i32::MAX],
+            vec![f64::INFINITY, 5.0, 5.0],
+            vec![0.0, f64::INFINITY, 5.0],
+            vec![0.0, 0.0, f64::INFINITY],
         ]);
     }
 
     #[test]
     fn simple_floyd_warshall_reversed() {
         let edge_weights = vec![
-            vec![0, 0, 0],
-            vec![5, 0, 0],
-            vec![1, 5, 0],
+            vec![0.0, 0.0, 0.0],
+            vec![5.0, 0.0, 0.0],
+            vec![1.0, 5.0, 0.0],
         ];
         let widest_paths = floyd_warshall_widest_paths(&edge_weights);
         assert_eq!(widest_paths, vec![
-            vec![i32::MAX, 0, 0],
-            vec![5, i32::MAX, 0],
-            vec![5, 5, i32::MAX],
+            vec![f64::INFINITY, 0.0, 0.0],
+            vec![5.0, f64::INFINITY, 0.0],
+            vec![5.0, 5.0, f64::INFINITY],
         ]);
     }
 
     #[test]
     fn floyd_warshall_chain() {
         let edge_weights = vec![
-            vec![0, 5, 1],
-            vec![1, 0, 5],
-            vec![3, 1, 0],
+            vec![0.0, 5.0, 1.0],
+            vec![1.0, 0.0, 5.0],
+            vec![3.0, 1.0, 0.0],
         ];
         let widest_paths = floyd_warshall_widest_paths(&edge_weights);
         assert_eq!(widest_paths, vec![
-            vec![i32::MAX, 5, 5],
-            vec![3, i32::MAX, 5],
-            vec![3, 3, i32::MAX],
+            vec![f64::INFINITY, 5.0, 5.0],
+            vec![3.0, f64::INFINITY, 5.0],
+            vec![3.0, 3.0, f64::INFINITY],
         ]);
     }
 
     #[test]
     fn complex_floyd_warshall() {
         let edge_weights = vec![
-            vec![0, 5, 1, 0],
-            vec![1, 0, 5, 0],
-            vec![0, 1, 0, 5],
-            vec![3, 0, 1, 0],
+            vec![0.0, 5.0, 1.0, 0.0],
+            vec![1.0, 0.0, 5.0, 0.0],
+            vec![0.0, 1.0, 0.0, 5.0],
+            vec![3.0, 0.0, 1.0, 0.0],
         ];
         let widest_paths = floyd_warshall_widest_paths(&edge_weights);
         assert_eq!(widest_paths, vec![
-            vec![i32::MAX, 5, 5, 5],
-            vec![3, i32::MAX, 5, 5],
-            vec![3, 3, i32::MAX, 5],
-            vec![3, 3, 3, i32::MAX],
+            vec![f64::INFINITY, 5.0, 5.0, 5.0],
+            vec![3.0, f64::INFINITY, 5.0, 5.0],
+            vec![3.0, 3.0, f64::INFINITY, 5.0],
+            vec![3.0, 3.0, 3.0, f64::INFINITY],
+        ]);
+    }
+
+    #[test]
+    fn link_strengths_winning_zeroes_losing_edge() {
+        let counts = vec![
+            vec![0, 3],
+            vec![5, 0],
+        ];
+        let strengths = link_strengths(&counts, Variant::Winning);
+        assert_eq!(strengths, vec![
+            vec![0.0, 0.0],
+            vec![5.0, 0.0],
         ]);
     }
+
+    #[test]
+    fn link_strengths_margin_can_be_negative() {
+        let counts = vec![
+            vec![0, 3],
+            vec![5, 0],
+        ];
+        let strengths = link_strengths(&counts, Variant::Margin);
+        assert_eq!(strengths, vec![
+            vec![0.0, -2.0],
+            vec![2.0, 0.0],
+        ]);
+    }
+
+    #[test]
+    fn link_strengths_ratio_of_zero_opposition_is_infinite() {
+        let counts = vec![
+            vec![0, 3],
+            vec![0, 0],
+        ];
+        let strengths = link_strengths(&counts, Variant::Ratio);
+        assert_eq!(strengths[0][1], f64::INFINITY);
+    }
+
+    #[test]
+    fn link_strengths_ratio_of_an_uncompared_pair_is_zero() {
+        let counts = vec![
+            vec![0, 0],
+            vec![0, 0],
+        ];
+        let strengths = link_strengths(&counts, Variant::Ratio);
+        assert_eq!(strengths, vec![
+            vec![0.0, 0.0],
+            vec![0.0, 0.0],
+        ]);
+    }
+
+    #[test]
+    fn smith_set_is_just_the_condorcet_winner() {
+        // ALICE always beats BOB and CHAD.
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3)],
+            vec![(ALICE, 1), (CHAD, 2), (BOB, 3)],
+        ];
+        assert_eq!(smith_set(ballots.clone()), vec![ALICE]);
+        assert_eq!(schwartz_set(ballots), vec![ALICE]);
+    }
+
+    #[test]
+    fn smith_and_schwartz_set_merge_a_condorcet_cycle() {
+        // ALICE beats BOB, BOB beats CHAD, CHAD beats ALICE (a cycle), and DAVE loses to everyone.
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3), (DAVE, 4)],
+            vec![(BOB, 1), (CHAD, 2), (ALICE, 3), (DAVE, 4)],
+            vec![(CHAD, 1), (ALICE, 2), (BOB, 3), (DAVE, 4)],
+        ];
+
+        let mut smith = smith_set(ballots.clone());
+        smith.sort_by_key(|candidate| candidate.id);
+        assert_eq!(smith, vec![ALICE, BOB, CHAD]);
+
+        let mut schwartz = schwartz_set(ballots);
+        schwartz.sort_by_key(|candidate| candidate.id);
+        assert_eq!(schwartz, vec![ALICE, BOB, CHAD]);
+    }
 }
@@ -0,0 +1,519 @@
+use crate::fraction::Fraction;
+use crate::{highest_id, unique_candidates, Ballot, BallotSlice, Candidate};
+use std::collections::{HashMap, HashSet};
+
+/// Single Transferable Vote: elects `seats` candidates from ranked `ballots` using a Droop quota
+/// and Weighted Inclusive Gregory surplus transfer.
+///
+/// In each round, any continuing candidate whose tally meets or exceeds the quota is elected and
+/// their surplus (tally minus quota) is transferred to the next continuing preference on each of
+/// their ballots, scaled by `surplus / total_current_value_of_transferable_ballots`. If no
+/// candidate meets quota, the candidate with the fewest votes is eliminated and their ballots
+/// transfer at full value. Ties are broken arbitrarily.
+///
+/// Returns the elected candidates, in the order they were elected.
+///
+/// See [reference](https://en.wikipedia.org/wiki/Single_transferable_vote) for more information.
+pub fn single_transferable_vote(ballots: Vec<Ballot>, seats: usize) -> Vec<Candidate> {
+    for ballot in ballots.iter() {
+        assert!(unique_candidates(ballot));
+    }
+    assert!(seats > 0, "must elect at least one seat");
+
+    let num_candidates = highest_id(&ballots) + 1;
+    let quota = droop_quota(ballots.len(), seats);
+
+    let mut weighted: Vec<WeightedBallot> = ballots
+        .iter()
+        .map(|ballot| WeightedBallot {
+            ballot,
+            value: Fraction::whole(1),
+        })
+        .collect();
+
+    let mut continuing: HashSet<usize> = (0..num_candidates).collect();
+    let mut elected = vec![];
+
+    while elected.len() < seats && !continuing.is_empty() {
+        let tally = first_preference_tally(&weighted, &continuing);
+        let remaining_seats = seats - elected.len();
+
+        // If there are no more candidates than remaining seats, everyone still in the running
+        // wins without needing to meet quota.
+        if continuing.len() <= remaining_seats {
+            let mut fill: Vec<(usize, Fraction)> = tally.into_iter().collect();
+            fill.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            elected.extend(fill.into_iter().map(|(id, _)| Candidate::from(id)));
+            break;
+        }
+
+        if let Some((&winner_id, &winner_votes)) = tally
+            .iter()
+            .filter(|(_, votes)| **votes >= quota)
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+        {
+            elected.push(Candidate::from(winner_id));
+            let surplus = winner_votes - quota;
+            transfer_surplus(&mut weighted, &continuing, winner_id, surplus);
+            continuing.remove(&winner_id);
+        } else {
+            // No one meets quota: eliminate the weakest candidate. Their ballots transfer at
+            // full value on the next round, since no ballots are held back.
+            let (&loser_id, _) = tally
+                .iter()
+                .min_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .unwrap();
+            continuing.remove(&loser_id);
+        }
+    }
+
+    elected
+}
+
+/// Returns `floor(total_valid_ballots / (seats + 1)) + 1`, the number of votes a candidate needs
+/// to guarantee election.
+fn droop_quota(total_valid_ballots: usize, seats: usize) -> Fraction {
+    Fraction::whole((total_valid_ballots / (seats + 1) + 1) as i64)
+}
+
+struct WeightedBallot<'a> {
+    ballot: &'a BallotSlice,
+    value: Fraction,
+}
+
+/// The continuing candidate a ballot currently counts towards, i.e. its most preferred candidate
+/// that hasn't already been elected or eliminated.
+fn current_preference(ballot: &BallotSlice, continuing: &HashSet<usize>) -> Option<Candidate> {
+    ballot
+        .iter()
+        .filter(|(candidate, _)| continuing.contains(&candidate.id))
+        .min_by_key(|(_, rank)| *rank)
+        .map(|(candidate, _)| *candidate)
+}
+
+fn first_preference_tally(
+    weighted: &[WeightedBallot],
+    continuing: &HashSet<usize>,
+) -> HashMap<usize, Fraction> {
+    let mut tally: HashMap<usize, Fraction> =
+        continuing.iter().map(|&id| (id, Fraction::whole(0))).collect();
+
+    for ballot in weighted {
+        if let Some(candidate) = current_preference(ballot.ballot, continuing) {
+            let total = tally.get_mut(&candidate.id).unwrap();
+            *total = *total + ballot.value;
+        }
+    }
+
+    tally
+}
+
+/// Scales down the value of every ballot currently held by `winner_id` that has a further
+/// continuing preference, so that together they carry exactly `surplus` votes forward. Ballots
+/// with no further continuing preference are left as-is; they are exhausted and will never be
+/// counted again since `winner_id` is no longer continuing.
+fn transfer_surplus(
+    weighted: &mut [WeightedBallot],
+    continuing: &HashSet<usize>,
+    winner_id: usize,
+    surplus: Fraction,
+) {
+    if surplus <= Fraction::whole(0) {
+        return;
+    }
+
+    let without_winner: HashSet<usize> =
+        continuing.iter().copied().filter(|&id| id != winner_id).collect();
+
+    let transferable: Vec<usize> = weighted
+        .iter()
+        .enumerate()
+        .filter(|(_, ballot)| {
+            current_preference(ballot.ballot, continuing) == Some(Candidate::from(winner_id))
+                && current_preference(ballot.ballot, &without_winner).is_some()
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    if transferable.is_empty() {
+        return;
+    }
+
+    let transferable_value = transferable
+        .iter()
+        .fold(Fraction::whole(0), |total, &i| total + weighted[i].value);
+
+    let transfer_value = surplus / transferable_value;
+    for i in transferable {
+        weighted[i].value = weighted[i].value * transfer_value;
+    }
+}
+
+/// Identifies a group of candidates that a [`Constraint`] places a seat-count requirement on
+/// (e.g. a region, a party list, a gender category).
+pub type CategoryId = usize;
+
+/// Requires that between `min` and `max` (inclusive) of the elected seats go to candidates in
+/// `category`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Constraint {
+    pub category: CategoryId,
+    pub min: usize,
+    pub max: usize,
+}
+
+/// Returned by [`stv_with_constraints`] when no assignment of seats can satisfy every
+/// [`Constraint`] at once, given how the count currently stands for `category`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InfeasibleConstraints {
+    pub category: CategoryId,
+}
+
+/// Single Transferable Vote, same as [`single_transferable_vote`], but additionally enforces a
+/// set of [`Constraint`]s on how many seats go to each category a candidate belongs to.
+///
+/// After each round, candidates are marked *guarded* (never eligible for elimination) when
+/// electing every remaining continuing candidate in their category is the only way left to meet
+/// that category's `min`, and *doomed* (eliminated at the next opportunity, regardless of tally)
+/// once their category has already reached its `max`. Feasibility of every constraint's `[min,
+/// max]` interval is re-checked every round; if it can no longer be satisfied, this returns
+/// `Err`.
+pub fn stv_with_constraints(
+    ballots: Vec<Ballot>,
+    seats: usize,
+    constraints: Vec<Constraint>,
+    candidate_categories: HashMap<Candidate, Vec<CategoryId>>,
+) -> Result<Vec<Candidate>, InfeasibleConstraints> {
+    for ballot in ballots.iter() {
+        assert!(unique_candidates(ballot));
+    }
+    assert!(seats > 0, "must elect at least one seat");
+
+    let num_candidates = highest_id(&ballots) + 1;
+    let quota = droop_quota(ballots.len(), seats);
+
+    let mut weighted: Vec<WeightedBallot> = ballots
+        .iter()
+        .map(|ballot| WeightedBallot {
+            ballot,
+            value: Fraction::whole(1),
+        })
+        .collect();
+
+    let mut continuing: HashSet<usize> = (0..num_candidates).collect();
+    let mut elected: Vec<Candidate> = vec![];
+
+    while elected.len() < seats && !continuing.is_empty() {
+        check_feasible(&constraints, &candidate_categories, &elected, &continuing)?;
+
+        let doomed = doomed_candidates(&constraints, &candidate_categories, &elected, &continuing);
+        if let Some(&loser_id) = doomed.iter().min() {
+            // Doomed candidates can never be elected: evict one now, regardless of their tally.
+            continuing.remove(&loser_id);
+            continue;
+        }
+
+        let guarded = guarded_candidates(&constraints, &candidate_categories, &elected, &continuing);
+        let tally = first_preference_tally(&weighted, &continuing);
+        let remaining_seats = seats - elected.len();
+
+        if continuing.len() <= remaining_seats {
+            let mut fill: Vec<(usize, Fraction)> = tally.into_iter().collect();
+            fill.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            elected.extend(fill.into_iter().map(|(id, _)| Candidate::from(id)));
+            break;
+        }
+
+        if let Some((&winner_id, &winner_votes)) = tally
+            .iter()
+            .filter(|(_, votes)| **votes >= quota)
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+        {
+            elected.push(Candidate::from(winner_id));
+            let surplus = winner_votes - quota;
+            transfer_surplus(&mut weighted, &continuing, winner_id, surplus);
+            continuing.remove(&winner_id);
+        } else {
+            let loser_id = tally
+                .iter()
+                .filter(|(id, _)| !guarded.contains(id))
+                .min_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map(|(&id, _)| id);
+
+            match loser_id {
+                Some(loser_id) => {
+                    continuing.remove(&loser_id);
+                }
+                None => {
+                    // Every continuing candidate is guarded, so no one can be eliminated: the
+                    // constraints can no longer be jointly satisfied, even though
+                    // `check_feasible` didn't already catch it this round.
+                    let category = constraints
+                        .iter()
+                        .find(|constraint| guarded_candidates(
+                            std::slice::from_ref(constraint),
+                            &candidate_categories,
+                            &elected,
+                            &continuing,
+                        ).len() == continuing.len())
+                        .map(|constraint| constraint.category)
+                        .unwrap_or(constraints[0].category);
+                    return Err(InfeasibleConstraints { category });
+                }
+            }
+        }
+    }
+
+    // A quota election can fill the last seat while another category's `min` is still unmet, so
+    // check the final result against every constraint.
+    let elected_counts = category_counts(&candidate_categories, elected.iter().map(|c| c.id));
+    for constraint in constraints.iter() {
+        let elected_count = *elected_counts.get(&constraint.category).unwrap_or(&0);
+        if elected_count < constraint.min || elected_count > constraint.max {
+            return Err(InfeasibleConstraints { category: constraint.category });
+        }
+    }
+
+    Ok(elected)
+}
+
+/// Tallies, by category, how many of `members` (candidate ids) belong to it. A candidate can
+/// belong to more than one category, or none.
+fn category_counts(
+    candidate_categories: &HashMap<Candidate, Vec<CategoryId>>,
+    members: impl Iterator<Item = usize>,
+) -> HashMap<CategoryId, usize> {
+    let mut counts = HashMap::new();
+    for id in members {
+        if let Some(categories) = candidate_categories.get(&Candidate::from(id)) {
+            for &category in categories {
+                *counts.entry(category).or_insert(0) += 1;
+            }
+        }
+    }
+    counts
+}
+
+fn candidate_in_category(
+    candidate_categories: &HashMap<Candidate, Vec<CategoryId>>,
+    id: usize,
+    category: CategoryId,
+) -> bool {
+    candidate_categories
+        .get(&Candidate::from(id))
+        .map(|categories| categories.contains(&category))
+        .unwrap_or(false)
+}
+
+/// Checks every constraint's `[min, max]` interval against how many seats its category has
+/// already won and how many continuing candidates in that category are left to fill it.
+///
+/// This only checks each constraint in isolation; joint infeasibility across overlapping
+/// categories (e.g. two categories that together demand more seats than remain, once a candidate
+/// who could cover both is gone) is instead caught by the caller, which returns `Err` when every
+/// continuing candidate turns out to be guarded, or when the final result breaks a constraint.
+fn check_feasible(
+    constraints: &[Constraint],
+    candidate_categories: &HashMap<Candidate, Vec<CategoryId>>,
+    elected: &[Candidate],
+    continuing: &HashSet<usize>,
+) -> Result<(), InfeasibleConstraints> {
+    let elected_counts = category_counts(candidate_categories, elected.iter().map(|c| c.id));
+    let continuing_counts = category_counts(candidate_categories, continuing.iter().copied());
+
+    for constraint in constraints {
+        let elected_count = *elected_counts.get(&constraint.category).unwrap_or(&0);
+        let continuing_count = *continuing_counts.get(&constraint.category).unwrap_or(&0);
+
+        if elected_count > constraint.max || elected_count + continuing_count < constraint.min {
+            return Err(InfeasibleConstraints { category: constraint.category });
+        }
+    }
+
+    Ok(())
+}
+
+/// Candidates who must never be eliminated: their category has no more continuing candidates
+/// than it still needs to meet its `min`.
+fn guarded_candidates(
+    constraints: &[Constraint],
+    candidate_categories: &HashMap<Candidate, Vec<CategoryId>>,
+    elected: &[Candidate],
+    continuing: &HashSet<usize>,
+) -> HashSet<usize> {
+    let elected_counts = category_counts(candidate_categories, elected.iter().map(|c| c.id));
+    let continuing_counts = category_counts(candidate_categories, continuing.iter().copied());
+
+    let mut guarded = HashSet::new();
+    for constraint in constraints {
+        let elected_count = *elected_counts.get(&constraint.category).unwrap_or(&0);
+        let continuing_count = *continuing_counts.get(&constraint.category).unwrap_or(&0);
+        let still_needed = constraint.min.saturating_sub(elected_count);
+
+        if still_needed > 0 && continuing_count <= still_needed {
+            for &id in continuing {
+                if candidate_in_category(candidate_categories, id, constraint.category) {
+                    guarded.insert(id);
+                }
+            }
+        }
+    }
+    guarded
+}
+
+/// Candidates who must never be elected: their category has already reached its `max`.
+fn doomed_candidates(
+    constraints: &[Constraint],
+    candidate_categories: &HashMap<Candidate, Vec<CategoryId>>,
+    elected: &[Candidate],
+    continuing: &HashSet<usize>,
+) -> HashSet<usize> {
+    let elected_counts = category_counts(candidate_categories, elected.iter().map(|c| c.id));
+
+    let mut doomed = HashSet::new();
+    for constraint in constraints {
+        let elected_count = *elected_counts.get(&constraint.category).unwrap_or(&0);
+        if elected_count >= constraint.max {
+            for &id in continuing {
+                if candidate_in_category(candidate_categories, id, constraint.category) {
+                    doomed.insert(id);
+                }
+            }
+        }
+    }
+    doomed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALICE: Candidate = Candidate { id: 0 };
+    const BOB: Candidate = Candidate { id: 1 };
+    const CHAD: Candidate = Candidate { id: 2 };
+    const ELSA: Candidate = Candidate { id: 3 };
+
+    #[test]
+    fn fills_remaining_seats_without_quota_when_candidates_equal_seats() {
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(BOB, 1), (ALICE, 2)],
+        ];
+        let mut winners = single_transferable_vote(ballots, 2);
+        winners.sort_by_key(|candidate| candidate.id);
+        assert_eq!(winners, vec![ALICE, BOB]);
+    }
+
+    #[test]
+    fn transfers_surplus_by_weighted_inclusive_gregory() {
+        // quota = floor(7 / 3) + 1 = 3
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(BOB, 1), (CHAD, 2)],
+            vec![(BOB, 1), (CHAD, 2)],
+            vec![(BOB, 1), (CHAD, 2)],
+        ];
+
+        let winners = single_transferable_vote(ballots, 2);
+        assert_eq!(winners, vec![ALICE, BOB]);
+    }
+
+    #[test]
+    fn surplus_transfer_value_accounts_for_ballots_already_fractional_from_an_earlier_transfer() {
+        // quota = floor(30 / 4) + 1 = 8.
+        let mut ballots = vec![vec![(ALICE, 1), (BOB, 2), (ELSA, 3)]; 16];
+        ballots.extend(vec![vec![(BOB, 1), (ELSA, 2)]; 8]);
+        ballots.extend(vec![vec![(CHAD, 1)]; 6]);
+
+        // ALICE is elected first, transferring 16 half-value ballots to BOB. BOB is then elected
+        // too, and must transfer the surplus scaled by the *value* still carried by the
+        // transferable ballots, not just their count, or ELSA's tally falls short of quota and
+        // CHAD wins the seat instead.
+        let winners = single_transferable_vote(ballots, 3);
+        assert_eq!(winners, vec![ALICE, BOB, ELSA]);
+    }
+
+    #[test]
+    fn doomed_candidate_is_eliminated_instead_of_elected_once_category_hits_max() {
+        const CATEGORY: CategoryId = 0;
+        // ALICE and BOB both belong to CATEGORY, which may contribute at most 1 of the 2 seats.
+        let candidate_categories =
+            HashMap::from([(ALICE, vec![CATEGORY]), (BOB, vec![CATEGORY])]);
+        let constraints = vec![Constraint { category: CATEGORY, min: 0, max: 1 }];
+
+        // quota = floor(10 / 3) + 1 = 4. ALICE and BOB both reach quota on first preferences, but
+        // BOB must lose out to the cap once ALICE is elected first.
+        let mut ballots = vec![vec![(ALICE, 1), (CHAD, 2)]; 5];
+        ballots.extend(vec![vec![(BOB, 1), (CHAD, 2)]; 4]);
+        ballots.push(vec![(CHAD, 1)]);
+
+        let winners =
+            stv_with_constraints(ballots, 2, constraints, candidate_categories).unwrap();
+        assert_eq!(winners, vec![ALICE, CHAD]);
+    }
+
+    #[test]
+    fn overlapping_category_minimums_covered_by_one_candidate_are_feasible() {
+        const CATEGORY_A: CategoryId = 0;
+        const CATEGORY_B: CategoryId = 1;
+        // ALICE belongs to both categories, so electing her alone covers both minimums; a check
+        // that sums each category's unmet minimum independently would double-count her and wrongly
+        // reject this as needing 2 seats when only 1 is available.
+        let candidate_categories = HashMap::from([(ALICE, vec![CATEGORY_A, CATEGORY_B])]);
+        let constraints = vec![
+            Constraint { category: CATEGORY_A, min: 1, max: 1 },
+            Constraint { category: CATEGORY_B, min: 1, max: 1 },
+        ];
+
+        let ballots = vec![vec![(ALICE, 1)]];
+
+        let winners =
+            stv_with_constraints(ballots, 1, constraints, candidate_categories).unwrap();
+        assert_eq!(winners, vec![ALICE]);
+    }
+
+    #[test]
+    fn jointly_infeasible_constraints_are_reported_as_an_error_instead_of_panicking() {
+        const CATEGORY_AB: CategoryId = 0;
+        const CATEGORY_C: CategoryId = 1;
+        // CATEGORY_AB and CATEGORY_C are individually satisfiable, but together they demand 3 of
+        // the 2 available seats.
+        let candidate_categories = HashMap::from([
+            (ALICE, vec![CATEGORY_AB]),
+            (BOB, vec![CATEGORY_AB]),
+            (CHAD, vec![CATEGORY_C]),
+        ]);
+        let constraints = vec![
+            Constraint { category: CATEGORY_AB, min: 2, max: 2 },
+            Constraint { category: CATEGORY_C, min: 1, max: 1 },
+        ];
+
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3)],
+            vec![(BOB, 1), (ALICE, 2), (CHAD, 3)],
+            vec![(CHAD, 1), (ALICE, 2), (BOB, 3)],
+        ];
+
+        let result = stv_with_constraints(ballots, 2, constraints, candidate_categories);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn infeasible_constraints_are_reported_as_an_error() {
+        const CATEGORY: CategoryId = 0;
+        // Only ALICE belongs to CATEGORY, but the constraint demands 2 seats from it.
+        let candidate_categories = HashMap::from([(ALICE, vec![CATEGORY])]);
+        let constraints = vec![Constraint { category: CATEGORY, min: 2, max: 2 }];
+
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2)],
+            vec![(BOB, 1), (ALICE, 2)],
+        ];
+
+        let result = stv_with_constraints(ballots, 2, constraints, candidate_categories);
+        assert_eq!(result, Err(InfeasibleConstraints { category: CATEGORY }));
+    }
+}
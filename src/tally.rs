@@ -0,0 +1,123 @@
+use crate::instant_runoff_voting::{instant_runoff_vote_weighted, TieBreak};
+use crate::schulze_method::{self, Variant};
+use crate::{unique_candidates, Ballot, BallotSlice, Candidate, PairwisePreferences};
+
+/// Accumulates ballots one at a time, so an election can be counted without the caller first
+/// materializing the full set of ballots as a `Vec<Ballot>`.
+///
+/// Ballots are fed in via [`Tally::add`] / [`Tally::add_weighted`], then the election is finalized
+/// with [`Tally::schulze_winners`] or [`Tally::irv_winner`]. A `Tally` can be finalized more than
+/// once, including with different methods, since adding ballots doesn't consume it.
+///
+/// Only the Schulze path is memory-bounded: it folds each ballot straight into a fixed-size
+/// [`PairwisePreferences`] and never needs the ballot again. [`Tally::irv_winner`] still needs
+/// every candidate's full remaining preference order after each elimination round, so `Tally`
+/// retains every `(Ballot, weight)` pair internally for it, the same as calling
+/// [`crate::instant_runoff_voting::instant_runoff_vote_weighted`] directly would.
+pub struct Tally {
+    pairwise: PairwisePreferences,
+    ballots: Vec<(Ballot, i32)>,
+}
+
+impl Tally {
+    /// Creates an empty tally for an election among `num_candidates` candidates (ids
+    /// `0..num_candidates`).
+    pub fn with_candidates(num_candidates: usize) -> Tally {
+        Tally {
+            pairwise: PairwisePreferences::new(num_candidates),
+            ballots: vec![],
+        }
+    }
+
+    /// Adds a single ballot to the tally.
+    pub fn add(&mut self, ballot: &BallotSlice) {
+        self.add_weighted(ballot, 1);
+    }
+
+    /// Adds `weight` copies of `ballot` to the tally, without needing to repeat it in memory.
+    /// Equivalent to calling [`Tally::add`] `weight` times.
+    pub fn add_weighted(&mut self, ballot: &BallotSlice, weight: i32) {
+        assert!(unique_candidates(ballot));
+        self.pairwise.count_ballot_weighted(ballot, weight);
+        self.ballots.push((ballot.to_vec(), weight));
+    }
+
+    /// Finalizes the tally and ranks the candidates by the Schulze method, using the
+    /// [`Variant::Winning`] link-strength measure.
+    pub fn schulze_winners(&self) -> Vec<Candidate> {
+        self.schulze_winners_with_variant(Variant::Winning)
+    }
+
+    /// Same as [`Tally::schulze_winners`], but lets the caller choose the link-strength
+    /// [`Variant`].
+    pub fn schulze_winners_with_variant(&self, variant: Variant) -> Vec<Candidate> {
+        schulze_method::schulze_ranking(&self.pairwise, variant)
+    }
+
+    /// Finalizes the tally and runs instant runoff voting over it. Ties are resolved by
+    /// `tie_break`.
+    pub fn irv_winner(&self, tie_break: TieBreak) -> Candidate {
+        instant_runoff_vote_weighted(self.ballots.clone(), tie_break)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALICE: Candidate = Candidate { id: 0 };
+    const BOB: Candidate = Candidate { id: 1 };
+    const CHAD: Candidate = Candidate { id: 2 };
+
+    #[test]
+    fn accumulates_ballots_one_at_a_time() {
+        let mut tally = Tally::with_candidates(2);
+        tally.add(&[(ALICE, 1), (BOB, 2)]);
+        tally.add(&[(ALICE, 1), (BOB, 2)]);
+        tally.add(&[(BOB, 1), (ALICE, 2)]);
+
+        assert_eq!(tally.schulze_winners(), vec![ALICE, BOB]);
+    }
+
+    #[test]
+    fn weighted_add_collapses_identical_ballots() {
+        let mut unweighted = Tally::with_candidates(3);
+        for _ in 0..5 {
+            unweighted.add(&[(ALICE, 1), (CHAD, 2), (BOB, 3)]);
+        }
+        unweighted.add(&[(BOB, 1), (CHAD, 2), (ALICE, 3)]);
+
+        let mut weighted = Tally::with_candidates(3);
+        weighted.add_weighted(&[(ALICE, 1), (CHAD, 2), (BOB, 3)], 5);
+        weighted.add_weighted(&[(BOB, 1), (CHAD, 2), (ALICE, 3)], 1);
+
+        assert_eq!(weighted.schulze_winners(), unweighted.schulze_winners());
+        assert_eq!(
+            weighted.irv_winner(TieBreak::Forwards { seed: 0 }),
+            unweighted.irv_winner(TieBreak::Forwards { seed: 0 }),
+        );
+    }
+
+    #[test]
+    fn irv_winner_matches_instant_runoff_vote() {
+        use crate::instant_runoff_voting::instant_runoff_vote;
+
+        let ballots = vec![
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3)],
+            vec![(ALICE, 1), (BOB, 2), (CHAD, 3)],
+            vec![(CHAD, 1), (BOB, 2), (ALICE, 3)],
+            vec![(BOB, 1), (CHAD, 2), (ALICE, 3)],
+            vec![(BOB, 1), (CHAD, 2), (ALICE, 3)],
+        ];
+
+        let mut tally = Tally::with_candidates(3);
+        for ballot in ballots.iter() {
+            tally.add(ballot);
+        }
+
+        assert_eq!(
+            tally.irv_winner(TieBreak::Forwards { seed: 0 }),
+            instant_runoff_vote(ballots, TieBreak::Forwards { seed: 0 }),
+        );
+    }
+}